@@ -1,37 +1,686 @@
+use std::cell::RefCell;
 use std::ffi::{CStr, CString};
 use std::os::raw::c_char;
-use tokenizers::Tokenizer;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+use tokenizers::decoders::byte_level::ByteLevel as ByteLevelDecoder;
+use tokenizers::decoders::wordpiece::WordPiece as WordPieceDecoder;
+use tokenizers::models::bpe::BPE;
+use tokenizers::models::unigram::Unigram;
+use tokenizers::models::wordpiece::WordPiece;
+use tokenizers::normalizers::bert::BertNormalizer;
+use tokenizers::pre_tokenizers::bert::BertPreTokenizer;
+use tokenizers::pre_tokenizers::byte_level::ByteLevel;
+use tokenizers::pre_tokenizers::metaspace::Metaspace;
+use tokenizers::processors::byte_level::ByteLevel as ByteLevelProcessor;
+use tokenizers::{PaddingParams, PaddingStrategy, Tokenizer, TruncationParams, TruncationStrategy};
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = RefCell::new(None);
+}
+
+fn set_last_error(message: impl Into<String>) {
+    let message = message.into();
+    let c_message = CString::new(message).unwrap_or_else(|_| {
+        CString::new("error message contained an interior NUL byte").unwrap()
+    });
+    LAST_ERROR.with(|slot| *slot.borrow_mut() = Some(c_message));
+}
+
+/// Returns a pointer to the last error message set on this thread, or null
+/// if there isn't one. The returned pointer is owned by the thread-local
+/// slot and is only valid until the next failing call on this thread.
+#[no_mangle]
+pub extern "C" fn tokenizer_last_error() -> *const c_char {
+    LAST_ERROR.with(|slot| match slot.borrow().as_ref() {
+        Some(message) => message.as_ptr(),
+        None => ptr::null(),
+    })
+}
+
+/// Runs `f`, catching both `Err` results and unwinding panics. On failure
+/// the message is stashed in [`LAST_ERROR`] and `default` is returned, so
+/// callers get a null/sentinel value instead of a crash across the FFI
+/// boundary.
+fn guard<T>(default: T, f: impl FnOnce() -> Result<T, String>) -> T {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(value)) => value,
+        Ok(Err(message)) => {
+            set_last_error(message);
+            default
+        }
+        Err(payload) => {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic in tokenizer FFI call".to_string());
+            set_last_error(message);
+            default
+        }
+    }
+}
+
+/// Converts a C string pointer to a `&str`, failing instead of panicking on
+/// a null pointer or invalid UTF-8.
+fn cstr_to_str<'a>(ptr: *const c_char) -> Result<&'a str, String> {
+    if ptr.is_null() {
+        return Err("null pointer passed where a string was expected".to_string());
+    }
+    unsafe { CStr::from_ptr(ptr) }
+        .to_str()
+        .map_err(|e| format!("invalid UTF-8 string: {e}"))
+}
+
+/// Parallel-array view over a `tokenizers::Encoding`, allocated by Rust and
+/// handed to the caller across the FFI boundary. Every `*_len` field gives
+/// the element count of the array it names; all arrays have the same length
+/// except `offsets`, which is flattened `(start, end)` pairs and so has
+/// twice as many elements as `ids_len`.
+#[repr(C)]
+pub struct CEncoding {
+    pub ids: *mut u32,
+    pub ids_len: usize,
+    pub attention_mask: *mut u32,
+    pub attention_mask_len: usize,
+    pub type_ids: *mut u32,
+    pub type_ids_len: usize,
+    pub special_tokens_mask: *mut u32,
+    pub special_tokens_mask_len: usize,
+    /// Flattened `(start, end)` byte offsets, one pair per token.
+    pub offsets: *mut usize,
+    pub offsets_len: usize,
+}
+
+/// Leaks `v` and returns its raw pointer and length. Goes through
+/// `into_boxed_slice` so the allocation's capacity is always shrunk to
+/// exactly `len` first — the `tokenizer_free_*` functions reconstruct with
+/// `Vec::from_raw_parts(ptr, len, len)`, which requires that.
+fn vec_into_raw_parts<T>(v: Vec<T>) -> (*mut T, usize) {
+    let boxed = v.into_boxed_slice();
+    let len = boxed.len();
+    let ptr = Box::into_raw(boxed) as *mut T;
+    (ptr, len)
+}
 
 #[no_mangle]
 pub extern "C" fn tokenizer_load(path: *const c_char) -> *mut Tokenizer {
-    let c_str = unsafe { CStr::from_ptr(path) };
-    let path_str = c_str.to_str().unwrap();
+    guard(ptr::null_mut(), || {
+        let path_str = cstr_to_str(path)?;
+        let tok = Tokenizer::from_file(path_str).map_err(|e| e.to_string())?;
+        Ok(Box::into_raw(Box::new(tok)))
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn tokenizer_from_bpe(
+    vocab_json: *const c_char,
+    merges_txt: *const c_char,
+) -> *mut Tokenizer {
+    guard(ptr::null_mut(), || {
+        let vocab_str = cstr_to_str(vocab_json)?;
+        let merges_str = cstr_to_str(merges_txt)?;
+
+        let bpe = BPE::from_file(vocab_str, merges_str)
+            .build()
+            .map_err(|e| e.to_string())?;
+
+        let mut tok = Tokenizer::new(bpe);
+        tok.with_pre_tokenizer(Some(ByteLevel::default()));
+        tok.with_post_processor(Some(ByteLevelProcessor::default()));
+        tok.with_decoder(Some(ByteLevelDecoder::default()));
+
+        Ok(Box::into_raw(Box::new(tok)))
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn tokenizer_from_wordpiece(
+    vocab_txt: *const c_char,
+    unk_token: *const c_char,
+) -> *mut Tokenizer {
+    guard(ptr::null_mut(), || {
+        let vocab_str = cstr_to_str(vocab_txt)?;
+        let unk_str = cstr_to_str(unk_token)?;
+
+        let wordpiece = WordPiece::from_file(vocab_str)
+            .unk_token(unk_str.to_string())
+            .build()
+            .map_err(|e| e.to_string())?;
 
-    let tok = Tokenizer::from_file(path_str).unwrap();
-    Box::into_raw(Box::new(tok))
+        let mut tok = Tokenizer::new(wordpiece);
+        tok.with_normalizer(Some(BertNormalizer::default()));
+        tok.with_pre_tokenizer(Some(BertPreTokenizer));
+        tok.with_decoder(Some(WordPieceDecoder::default()));
+
+        Ok(Box::into_raw(Box::new(tok)))
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn tokenizer_from_unigram(model_file: *const c_char) -> *mut Tokenizer {
+    guard(ptr::null_mut(), || {
+        let model_path = cstr_to_str(model_file)?;
+
+        let contents = std::fs::read_to_string(model_path).map_err(|e| e.to_string())?;
+        let unigram: Unigram = serde_json::from_str(&contents).map_err(|e| e.to_string())?;
+
+        let mut tok = Tokenizer::new(unigram);
+        tok.with_pre_tokenizer(Some(Metaspace::default()));
+        tok.with_decoder(Some(Metaspace::default()));
+
+        Ok(Box::into_raw(Box::new(tok)))
+    })
+}
+
+/// Downloads `tokenizer.json` for `model_id` (and optional `revision`, which
+/// may be null to mean `"main"`) from the Hugging Face Hub into a local
+/// cache directory, then loads it the same way [`tokenizer_load`] does.
+#[no_mangle]
+pub extern "C" fn tokenizer_from_pretrained(
+    model_id: *const c_char,
+    revision: *const c_char,
+) -> *mut Tokenizer {
+    guard(ptr::null_mut(), || {
+        let model_id_str = cstr_to_str(model_id)?;
+        let revision_str = if revision.is_null() {
+            "main"
+        } else {
+            cstr_to_str(revision)?
+        };
+
+        let cache_path = hub_cache_path(model_id_str, revision_str)?;
+        if !cache_path.exists() {
+            let url = format!(
+                "https://huggingface.co/{model_id_str}/resolve/{revision_str}/tokenizer.json"
+            );
+            let response = reqwest::blocking::get(&url).map_err(|e| e.to_string())?;
+            if !response.status().is_success() {
+                return Err(format!(
+                    "failed to download tokenizer for '{model_id_str}' ({revision_str}): HTTP {}",
+                    response.status()
+                ));
+            }
+            let bytes = response.bytes().map_err(|e| e.to_string())?;
+            std::fs::create_dir_all(cache_path.parent().unwrap()).map_err(|e| e.to_string())?;
+            std::fs::write(&cache_path, &bytes).map_err(|e| e.to_string())?;
+        }
+
+        let tok = Tokenizer::from_file(&cache_path).map_err(|e| e.to_string())?;
+        Ok(Box::into_raw(Box::new(tok)))
+    })
+}
+
+/// Derives the on-disk cache location for `model_id`/`revision` from a hash
+/// of both rather than the raw strings, so a crafted `model_id`/`revision`
+/// (e.g. containing `..` or `/`) can't make the cache path resolve outside
+/// the cache root.
+fn hub_cache_path(model_id: &str, revision: &str) -> Result<std::path::PathBuf, String> {
+    use std::hash::{Hash, Hasher};
+
+    let home = std::env::var("HOME").map_err(|_| "HOME is not set".to_string())?;
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    model_id.hash(&mut hasher);
+    revision.hash(&mut hasher);
+    let digest = hasher.finish();
+
+    Ok(std::path::Path::new(&home)
+        .join(".cache")
+        .join("videogenerator")
+        .join("tokenizers")
+        .join(format!("{digest:016x}"))
+        .join("tokenizer.json"))
+}
+
+/// Configures truncation on `handle`. `strategy` is `0` for longest-first,
+/// `1` for only-first, `2` for only-second. Returns `0` on success and `-1`
+/// on failure, with the reason available from [`tokenizer_last_error`].
+#[no_mangle]
+pub extern "C" fn tokenizer_set_truncation(
+    handle: *mut Tokenizer,
+    max_length: usize,
+    strategy: i32,
+    stride: usize,
+) -> i32 {
+    guard(-1, || {
+        if handle.is_null() {
+            return Err("null tokenizer handle".to_string());
+        }
+        let tok = unsafe { &mut *handle };
+
+        let strategy = match strategy {
+            0 => TruncationStrategy::LongestFirst,
+            1 => TruncationStrategy::OnlyFirst,
+            2 => TruncationStrategy::OnlySecond,
+            other => return Err(format!("unknown truncation strategy: {other}")),
+        };
+
+        tok.with_truncation(Some(TruncationParams {
+            max_length,
+            strategy,
+            stride,
+            ..Default::default()
+        }))
+        .map_err(|e| e.to_string())?;
+
+        Ok(0)
+    })
+}
+
+/// Configures padding on `handle`. `strategy` is `0` to pad each batch to
+/// its longest sequence, `1` to pad every sequence to `max_length`. Returns
+/// `0` on success and `-1` on failure, with the reason available from
+/// [`tokenizer_last_error`].
+#[no_mangle]
+pub extern "C" fn tokenizer_set_padding(
+    handle: *mut Tokenizer,
+    strategy: i32,
+    pad_id: u32,
+    pad_token: *const c_char,
+    max_length: usize,
+) -> i32 {
+    guard(-1, || {
+        if handle.is_null() {
+            return Err("null tokenizer handle".to_string());
+        }
+        let tok = unsafe { &mut *handle };
+        let pad_token_str = cstr_to_str(pad_token)?;
+
+        let strategy = match strategy {
+            0 => PaddingStrategy::BatchLongest,
+            1 => PaddingStrategy::Fixed(max_length),
+            other => return Err(format!("unknown padding strategy: {other}")),
+        };
+
+        tok.with_padding(Some(PaddingParams {
+            strategy,
+            pad_id,
+            pad_token: pad_token_str.to_string(),
+            ..Default::default()
+        }));
+
+        Ok(0)
+    })
 }
 
 #[no_mangle]
 pub extern "C" fn tokenizer_encode(handle: *mut Tokenizer, text: *const c_char) -> *mut c_char {
-    let tok = unsafe { &mut *handle };
-    let c_str = unsafe { CStr::from_ptr(text) };
-    let text_str = c_str.to_str().unwrap();
+    guard(ptr::null_mut(), || {
+        if handle.is_null() {
+            return Err("null tokenizer handle".to_string());
+        }
+        let tok = unsafe { &mut *handle };
+        let text_str = cstr_to_str(text)?;
+
+        let enc = tok.encode(text_str, true).map_err(|e| e.to_string())?;
+        let ids: Vec<String> = enc.get_ids().iter().map(|id| id.to_string()).collect();
+        let result = ids.join(",");
+
+        Ok(CString::new(result).map_err(|e| e.to_string())?.into_raw())
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn tokenizer_encode_ids(
+    handle: *mut Tokenizer,
+    text: *const c_char,
+    out_len: *mut usize,
+) -> *mut u32 {
+    guard(ptr::null_mut(), || {
+        if handle.is_null() {
+            return Err("null tokenizer handle".to_string());
+        }
+        if out_len.is_null() {
+            return Err("null out_len pointer".to_string());
+        }
+        let tok = unsafe { &mut *handle };
+        let text_str = cstr_to_str(text)?;
+
+        let enc = tok.encode(text_str, true).map_err(|e| e.to_string())?;
+        let (ptr, len) = vec_into_raw_parts(enc.get_ids().to_vec());
+        unsafe { *out_len = len };
+        Ok(ptr)
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn tokenizer_free_ids(ptr: *mut u32, len: usize) {
+    guard((), || {
+        if ptr.is_null() {
+            return Ok(());
+        }
+        unsafe { drop(Vec::from_raw_parts(ptr, len, len)) };
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn tokenizer_decode(
+    handle: *mut Tokenizer,
+    ids_ptr: *const u32,
+    ids_len: usize,
+    skip_special_tokens: bool,
+) -> *mut c_char {
+    guard(ptr::null_mut(), || {
+        if handle.is_null() {
+            return Err("null tokenizer handle".to_string());
+        }
+        if ids_ptr.is_null() && ids_len > 0 {
+            return Err("null ids pointer with non-zero length".to_string());
+        }
+        let tok = unsafe { &mut *handle };
+        let ids: &[u32] = if ids_len == 0 {
+            &[]
+        } else {
+            unsafe { slice::from_raw_parts(ids_ptr, ids_len) }
+        };
+
+        let text = tok
+            .decode(ids, skip_special_tokens)
+            .map_err(|e| e.to_string())?;
+
+        Ok(CString::new(text).map_err(|e| e.to_string())?.into_raw())
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn tokenizer_encode_full(
+    handle: *mut Tokenizer,
+    text: *const c_char,
+    add_special_tokens: bool,
+) -> *mut CEncoding {
+    guard(ptr::null_mut(), || {
+        if handle.is_null() {
+            return Err("null tokenizer handle".to_string());
+        }
+        let tok = unsafe { &mut *handle };
+        let text_str = cstr_to_str(text)?;
+
+        let enc = tok
+            .encode(text_str, add_special_tokens)
+            .map_err(|e| e.to_string())?;
+
+        let (ids, ids_len) = vec_into_raw_parts(enc.get_ids().to_vec());
+        let (attention_mask, attention_mask_len) =
+            vec_into_raw_parts(enc.get_attention_mask().to_vec());
+        let (type_ids, type_ids_len) = vec_into_raw_parts(enc.get_type_ids().to_vec());
+        let (special_tokens_mask, special_tokens_mask_len) =
+            vec_into_raw_parts(enc.get_special_tokens_mask().to_vec());
+
+        let offsets_flat: Vec<usize> = enc
+            .get_offsets()
+            .iter()
+            .flat_map(|(start, end)| [*start, *end])
+            .collect();
+        let (offsets, offsets_len) = vec_into_raw_parts(offsets_flat);
+
+        Ok(Box::into_raw(Box::new(CEncoding {
+            ids,
+            ids_len,
+            attention_mask,
+            attention_mask_len,
+            type_ids,
+            type_ids_len,
+            special_tokens_mask,
+            special_tokens_mask_len,
+            offsets,
+            offsets_len,
+        })))
+    })
+}
+
+/// Encodes `count` null-terminated strings from `texts` in one batch and
+/// returns a flat, row-major `count x out_cols` matrix of token IDs, using
+/// whatever truncation was configured via [`tokenizer_set_truncation`].
+/// Padding must already be configured via [`tokenizer_set_padding`] — short
+/// rows are filled out to `out_cols` with the configured `pad_id` rather
+/// than an arbitrary value, and the call fails if no padding is set. Under
+/// `Fixed(max_length)` padding, `out_cols` is always `max_length`, and the
+/// call fails instead of silently widening the matrix if any row encodes
+/// longer than that. Free the returned buffer with
+/// `tokenizer_free_ids(ptr, out_rows * out_cols)`.
+#[no_mangle]
+pub extern "C" fn tokenizer_encode_batch(
+    handle: *mut Tokenizer,
+    texts: *const *const c_char,
+    count: usize,
+    out_rows: *mut usize,
+    out_cols: *mut usize,
+) -> *mut u32 {
+    guard(ptr::null_mut(), || {
+        if handle.is_null() {
+            return Err("null tokenizer handle".to_string());
+        }
+        if (texts.is_null() && count > 0) || out_rows.is_null() || out_cols.is_null() {
+            return Err("null pointer passed to tokenizer_encode_batch".to_string());
+        }
+        let tok = unsafe { &mut *handle };
+        let (pad_id, strategy) = match tok.get_padding() {
+            Some(params) => (params.pad_id, params.strategy),
+            None => {
+                return Err(
+                    "padding must be configured via tokenizer_set_padding before batch encoding"
+                        .to_string(),
+                )
+            }
+        };
+        let text_ptrs: &[*const c_char] = if count == 0 {
+            &[]
+        } else {
+            unsafe { slice::from_raw_parts(texts, count) }
+        };
+        let texts: Vec<&str> = text_ptrs
+            .iter()
+            .map(|ptr| cstr_to_str(*ptr))
+            .collect::<Result<_, _>>()?;
+
+        let encodings = tok
+            .encode_batch(texts, true)
+            .map_err(|e| e.to_string())?;
 
-    let enc = tok.encode(text_str, true).unwrap();
-    let ids: Vec<String> = enc.get_ids().iter().map(|id| id.to_string()).collect();
-    let result = ids.join(",");
+        let cols = match strategy {
+            PaddingStrategy::Fixed(max_length) => {
+                for enc in &encodings {
+                    if enc.get_ids().len() > max_length {
+                        return Err(format!(
+                            "encoded sequence length {} exceeds configured padding max_length {max_length}",
+                            enc.get_ids().len()
+                        ));
+                    }
+                }
+                max_length
+            }
+            PaddingStrategy::BatchLongest => {
+                encodings.iter().map(|e| e.get_ids().len()).max().unwrap_or(0)
+            }
+        };
+        let mut flat = vec![pad_id; count * cols];
+        for (row, enc) in encodings.iter().enumerate() {
+            let ids = enc.get_ids();
+            flat[row * cols..row * cols + ids.len()].copy_from_slice(ids);
+        }
 
-    CString::new(result).unwrap().into_raw()
+        unsafe {
+            *out_rows = count;
+            *out_cols = cols;
+        }
+
+        let (ptr, _) = vec_into_raw_parts(flat);
+        Ok(ptr)
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn tokenizer_free_encoding(encoding: *mut CEncoding) {
+    guard((), || {
+        if encoding.is_null() {
+            return Ok(());
+        }
+        unsafe {
+            let enc = Box::from_raw(encoding);
+            drop(Vec::from_raw_parts(enc.ids, enc.ids_len, enc.ids_len));
+            drop(Vec::from_raw_parts(
+                enc.attention_mask,
+                enc.attention_mask_len,
+                enc.attention_mask_len,
+            ));
+            drop(Vec::from_raw_parts(enc.type_ids, enc.type_ids_len, enc.type_ids_len));
+            drop(Vec::from_raw_parts(
+                enc.special_tokens_mask,
+                enc.special_tokens_mask_len,
+                enc.special_tokens_mask_len,
+            ));
+            drop(Vec::from_raw_parts(enc.offsets, enc.offsets_len, enc.offsets_len));
+        }
+        Ok(())
+    })
 }
 
 #[no_mangle]
 pub extern "C" fn tokenizer_free_string(s: *mut c_char) {
-    if s.is_null() { return; }
-    unsafe { drop(CString::from_raw(s)) };
+    guard((), || {
+        if s.is_null() {
+            return Ok(());
+        }
+        unsafe { drop(CString::from_raw(s)) };
+        Ok(())
+    })
 }
 
 #[no_mangle]
 pub extern "C" fn tokenizer_destroy(handle: *mut Tokenizer) {
-    if handle.is_null() { return; }
-    unsafe { drop(Box::from_raw(handle)) };
+    guard((), || {
+        if handle.is_null() {
+            return Ok(());
+        }
+        unsafe { drop(Box::from_raw(handle)) };
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static TEMP_FILE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    fn write_temp_file(name: &str, contents: &str) -> std::path::PathBuf {
+        let id = TEMP_FILE_COUNTER.fetch_add(1, Ordering::SeqCst);
+        let path = std::env::temp_dir().join(format!("ffi_test_{}_{id}_{name}", std::process::id()));
+        let mut file = std::fs::File::create(&path).unwrap();
+        file.write_all(contents.as_bytes()).unwrap();
+        path
+    }
+
+    fn build_bpe_tokenizer() -> *mut Tokenizer {
+        let vocab_path = write_temp_file("vocab.json", r#"{"a": 0, "b": 1, "ab": 2, "<unk>": 3}"#);
+        let merges_path = write_temp_file("merges.txt", "#version: 0.2\na b\n");
+
+        let vocab_c = CString::new(vocab_path.to_str().unwrap()).unwrap();
+        let merges_c = CString::new(merges_path.to_str().unwrap()).unwrap();
+
+        let handle = tokenizer_from_bpe(vocab_c.as_ptr(), merges_c.as_ptr());
+        assert!(!handle.is_null(), "tokenizer_from_bpe failed");
+        handle
+    }
+
+    #[test]
+    fn encode_then_decode_round_trips_and_frees_cleanly() {
+        let handle = build_bpe_tokenizer();
+        let text = CString::new("ab").unwrap();
+
+        let mut len = 0usize;
+        let ids_ptr = tokenizer_encode_ids(handle, text.as_ptr(), &mut len);
+        assert!(!ids_ptr.is_null());
+        assert!(len > 0);
+
+        let decoded_ptr = tokenizer_decode(handle, ids_ptr, len, false);
+        assert!(!decoded_ptr.is_null());
+        let decoded = unsafe { CStr::from_ptr(decoded_ptr) }
+            .to_str()
+            .unwrap()
+            .to_string();
+
+        tokenizer_free_ids(ids_ptr, len);
+        tokenizer_free_string(decoded_ptr);
+        tokenizer_destroy(handle);
+
+        assert_eq!(decoded.trim(), "ab");
+    }
+
+    #[test]
+    fn encode_full_round_trip_frees_cleanly() {
+        let handle = build_bpe_tokenizer();
+        let text = CString::new("ab").unwrap();
+
+        let encoding = tokenizer_encode_full(handle, text.as_ptr(), false);
+        assert!(!encoding.is_null());
+        assert!(unsafe { (*encoding).ids_len } > 0);
+
+        tokenizer_free_encoding(encoding);
+        tokenizer_destroy(handle);
+    }
+
+    #[test]
+    fn encode_batch_pads_to_fixed_max_length_with_configured_pad_id() {
+        let handle = build_bpe_tokenizer();
+
+        let pad_token = CString::new("<unk>").unwrap();
+        let status = tokenizer_set_padding(handle, 1, 3, pad_token.as_ptr(), 4);
+        assert_eq!(status, 0);
+
+        let short = CString::new("a").unwrap();
+        let long = CString::new("ab").unwrap();
+        let texts = [short.as_ptr(), long.as_ptr()];
+
+        let mut rows = 0usize;
+        let mut cols = 0usize;
+        let matrix = tokenizer_encode_batch(handle, texts.as_ptr(), texts.len(), &mut rows, &mut cols);
+        assert!(!matrix.is_null());
+        assert_eq!(rows, 2);
+        assert_eq!(cols, 4);
+
+        let flat = unsafe { slice::from_raw_parts(matrix, rows * cols) };
+        assert_eq!(flat[cols - 1], 3, "unused tail of a row must be the configured pad_id, not 0");
+
+        tokenizer_free_ids(matrix, rows * cols);
+        tokenizer_destroy(handle);
+    }
+
+    #[test]
+    fn encode_batch_rejects_rows_longer_than_fixed_max_length() {
+        let handle = build_bpe_tokenizer();
+
+        let pad_token = CString::new("<unk>").unwrap();
+        tokenizer_set_padding(handle, 1, 3, pad_token.as_ptr(), 1);
+
+        // "ba" has no merge, so it encodes to two tokens: longer than max_length 1.
+        let long = CString::new("ba").unwrap();
+        let texts = [long.as_ptr()];
+
+        let mut rows = 0usize;
+        let mut cols = 0usize;
+        let matrix = tokenizer_encode_batch(handle, texts.as_ptr(), texts.len(), &mut rows, &mut cols);
+        assert!(matrix.is_null());
+
+        tokenizer_destroy(handle);
+    }
+
+    #[test]
+    fn hub_cache_path_stays_under_cache_root_for_hostile_input() {
+        let home = std::env::var("HOME").unwrap();
+        let root = std::path::Path::new(&home)
+            .join(".cache")
+            .join("videogenerator")
+            .join("tokenizers");
+
+        let path = hub_cache_path("../../../../tmp/evil", "../../etc").unwrap();
+        assert!(
+            path.starts_with(&root),
+            "cache path escaped the cache root: {path:?}"
+        );
+    }
 }
\ No newline at end of file